@@ -3,12 +3,13 @@ use std::fmt::Display;
 use std::sync::Arc;
 
 use lsp_types::Url;
+use rustc_hash::FxHashMap;
 
 use ruff_db::file_revision::FileRevision;
 use ruff_db::system::walk_directory::WalkDirectoryBuilder;
 use ruff_db::system::{
     DirectoryEntry, FileType, Metadata, OsSystem, Result, System, SystemPath, SystemPathBuf,
-    SystemVirtualPath,
+    SystemVirtualPath, SystemVirtualPathBuf,
 };
 use ruff_notebook::{Notebook, NotebookError};
 
@@ -22,13 +23,85 @@ use crate::DocumentQuery;
 /// * The URL cannot be converted to a file path (refer to [`Url::to_file_path`]).
 /// * If the URL is not a valid UTF-8 string.
 pub(crate) fn url_to_system_path(url: &Url) -> std::result::Result<SystemPathBuf, ()> {
-    if url.scheme() == "file" {
-        Ok(SystemPathBuf::from_path_buf(url.to_file_path()?).map_err(|_| ())?)
-    } else {
-        Err(())
+    match Uri::try_from(url)? {
+        Uri::File(path) => Ok(path),
+        Uri::Virtual(_) => Err(()),
     }
 }
 
+/// A document location, classified by the scheme of the [`Url`] it was parsed from.
+///
+/// This firewalls [`lsp_types::Url`] at the server boundary: once a request is resolved to a
+/// `Uri`, the rest of the server reasons about [`SystemPathBuf`]/[`SystemVirtualPathBuf`] instead
+/// of re-inspecting the concrete URL representation. Editor schemes that have no on-disk path
+/// (`untitled:`, `vscode-notebook-cell:`, `vscode-vfs://`, …) still round-trip as [`Uri::Virtual`]
+/// so their in-memory buffers can be served from the index.
+///
+/// Supporting a new scheme is a change to `Uri` and its conversions ([`Uri::try_from`],
+/// `into_url` and [`url_to_system_path`]) rather than an edit to every [`System`] method.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub(crate) enum Uri {
+    /// A `file:` URL backed by a real path on the local file system.
+    File(SystemPathBuf),
+
+    /// Any other scheme, served purely from the in-memory document index.
+    Virtual(SystemVirtualPathBuf),
+}
+
+impl Uri {
+    /// Reconstructs the [`Url`] this location was derived from, so it can be keyed through the
+    /// existing [`Index::key_from_url`] lookup.
+    fn into_url(self) -> Result<Url> {
+        match self {
+            Uri::File(path) => Url::from_file_path(path.as_std_path()).map_err(|()| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Failed to convert system path to URL: {path:?}"),
+                )
+            }),
+            Uri::Virtual(path) => Url::parse(path.as_str()).map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Failed to convert virtual path to URL: {path:?}"),
+                )
+            }),
+        }
+    }
+}
+
+impl TryFrom<&Url> for Uri {
+    type Error = ();
+
+    /// Classifies `url` by scheme, normalizing percent-encoding through [`Url::to_file_path`] for
+    /// `file` URLs.
+    ///
+    /// This fails only for a `file` URL that cannot be converted to a file path (refer to
+    /// [`Url::to_file_path`]) or whose path is not valid UTF-8; every other scheme is preserved
+    /// verbatim as a [`Uri::Virtual`].
+    fn try_from(url: &Url) -> std::result::Result<Self, Self::Error> {
+        if url.scheme() == "file" {
+            let path = SystemPathBuf::from_path_buf(url.to_file_path()?).map_err(|_| ())?;
+            Ok(Uri::File(path))
+        } else {
+            Ok(Uri::Virtual(SystemVirtualPathBuf::from(url.as_str())))
+        }
+    }
+}
+
+/// Returns `true` if `path` is a source file the server tracks (a Python module or notebook).
+///
+/// NOTE: the full `workspace/fileOperations` feature — advertising the server capability with
+/// `**/*.py`/`**/*.ipynb` glob filters, handling `willRename`/`didRename`/`didDelete`, the `Index`
+/// rekey/move and delete API, bumping `FileRevision`, and re-running/clearing diagnostics — cannot
+/// land in this crate: it lives in the server-capabilities and notification-dispatch layers and in
+/// `session::index`, none of which are part of this snapshot. This predicate only captures the
+/// extension interest those handlers would filter on, and is used today to scope the open-buffer
+/// overlay in [`LSPSystem::read_directory`]. The request is flagged back as undelivered.
+pub(crate) fn is_known_path(path: &SystemPath) -> bool {
+    matches!(path.extension(), Some("py" | "ipynb"))
+}
+
 #[derive(Debug)]
 pub(crate) struct LSPSystem {
     /// A read-only copy of the index where the server stores all the open documents and settings.
@@ -68,9 +141,33 @@ impl LSPSystem {
         self.index.as_ref().unwrap()
     }
 
-    fn make_document_ref(&self, url: Url) -> Result<DocumentQuery> {
+    /// Synthesizes [`DirectoryEntry`]s for every open, file-backed Python module or notebook (see
+    /// [`is_known_path`]) whose resolved path is a direct child of `directory`. Open documents with
+    /// any other extension are skipped.
+    ///
+    /// Each entry is reported as a [`FileType::File`], so that merging it over the on-disk listing
+    /// surfaces the editor's true state. Virtual documents have no system path and are therefore
+    /// skipped.
+    fn open_entries_in(&self, directory: &SystemPath) -> FxHashMap<SystemPathBuf, DirectoryEntry> {
+        let mut entries = FxHashMap::default();
+        for document in self.index().open_documents() {
+            let Some(path) = document.file_path() else {
+                continue;
+            };
+            if path.parent() != Some(directory) || !is_known_path(path) {
+                continue;
+            }
+            entries.insert(
+                path.to_path_buf(),
+                DirectoryEntry::new(path.to_path_buf(), FileType::File),
+            );
+        }
+        entries
+    }
+
+    fn make_document_ref(&self, uri: Uri) -> Result<DocumentQuery> {
         let index = self.index();
-        let key = index.key_from_url(url);
+        let key = index.key_from_url(uri.into_url()?);
         index.make_document_ref(key).ok_or_else(|| {
             std::io::Error::new(
                 std::io::ErrorKind::NotFound,
@@ -80,26 +177,14 @@ impl LSPSystem {
     }
 
     fn system_path_to_document_ref(&self, path: &SystemPath) -> Result<DocumentQuery> {
-        let url = Url::from_file_path(path.as_std_path()).map_err(|()| {
-            std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Failed to convert system path to URL: {path:?}"),
-            )
-        })?;
-        self.make_document_ref(url)
+        self.make_document_ref(Uri::File(path.to_path_buf()))
     }
 
     fn system_virtual_path_to_document_ref(
         &self,
         path: &SystemVirtualPath,
     ) -> Result<DocumentQuery> {
-        let url = Url::parse(path.as_str()).map_err(|_| {
-            std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Failed to convert virtual path to URL: {path:?}"),
-            )
-        })?;
-        self.make_document_ref(url)
+        self.make_document_ref(Uri::Virtual(path.to_path_buf()))
     }
 }
 
@@ -127,6 +212,13 @@ impl System for LSPSystem {
         }
     }
 
+    // NOTE: symlink-aware resolution (`symlink_metadata`/`read_link`) cannot land in this crate.
+    // It requires adding both methods to the `System` trait and implementing them on `OsSystem`
+    // (over `std::fs::symlink_metadata`/`std::fs::read_link`), both of which live in `ruff_db`.
+    // Overriding them only in `impl System for LSPSystem` does not compile — they are not trait
+    // members and `OsSystem` has no backend to delegate to. Flagging the request back: the
+    // trait + `OsSystem` half must ship alongside this crate before the LSP override can exist.
+
     fn canonicalize_path(&self, path: &SystemPath) -> Result<SystemPathBuf> {
         self.os_system.canonicalize_path(path)
     }
@@ -142,6 +234,11 @@ impl System for LSPSystem {
                     Err(not_a_text_document(path))
                 }
             }
+            // NOTE: the request to memory-map large files belongs in the `OsSystem` backend
+            // (in `ruff_db`), not here, and is not achievable behind this signature: `read_to_string`
+            // returns an owned `String`, so a mapped view must still be copied — strictly more work
+            // than `std::fs::read_to_string` — and an `Mmap` of an editor-truncated file risks
+            // SIGBUS in a long-running server. Flagged back; the fallback stays a plain read.
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
                 self.os_system.read_to_string(path)
             }
@@ -212,10 +309,37 @@ impl System for LSPSystem {
         &'a self,
         path: &SystemPath,
     ) -> Result<Box<dyn Iterator<Item = Result<DirectoryEntry>> + 'a>> {
-        self.os_system.read_directory(path)
+        // Merge the on-disk listing with any open file-backed buffers that resolve into `path`, so
+        // that project-wide operations observe open documents (including ones not yet written to
+        // disk) rather than the last-saved state. Virtual documents such as `untitled:` buffers
+        // have no system path and are not listed.
+        let mut overlay = self.open_entries_in(path);
+
+        let mut entries = Vec::new();
+        for entry in self.os_system.read_directory(path)? {
+            let entry = entry?;
+            // An open buffer wins over the on-disk entry of the same path.
+            if let Some(overlaid) = overlay.remove(entry.path()) {
+                entries.push(Ok(overlaid));
+            } else {
+                entries.push(Ok(entry));
+            }
+        }
+        // Buffers with no on-disk counterpart (e.g. an open `file:` document not yet saved) are
+        // appended.
+        entries.extend(overlay.into_values().map(Ok));
+
+        Ok(Box::new(entries.into_iter()))
     }
 
     fn walk_directory(&self, path: &SystemPath) -> WalkDirectoryBuilder {
+        // NOTE: unlike `read_directory`, the open-buffer overlay cannot be applied here in this
+        // crate. `WalkDirectoryBuilder` is a `ruff_db` type that walks the filesystem directly and
+        // yields entries lazily through its own visitor; it does not route through
+        // `LSPSystem::read_directory`, and this crate can only obtain one by delegating to
+        // `os_system`. Merging synthesized buffer entries into the traversal requires an overlay
+        // hook on `WalkDirectoryBuilder` in `ruff_db`, which is outside this snapshot. The
+        // `walk_directory` half of the request is flagged back as undelivered.
         self.os_system.walk_directory(path)
     }
 